@@ -78,7 +78,8 @@ interface ScanResult {
   height: number;
   timestamp: number;
   memo: string;
-  amount: number; // Amount in ZEC
+  zatoshis: number; // Exact value, use for comparisons
+  amount: string; // Amount in ZEC with 8 decimals, display only
 }
 
 export function ScanMyTransactions() {
@@ -125,7 +126,7 @@ export function ScanMyTransactions() {
     const minScanTime = 1500;
 
     try {
-      const { filterCompactOutputs, decryptMemo } = await import('@/lib/wasm-loader');
+      const { filterCompactOutputs, decryptMemo, formatZatoshis, memoText } = await import('@/lib/wasm-loader');
       const apiUrl = getApiUrl();
 
       // Check for cancellation
@@ -267,12 +268,13 @@ export function ScanMyTransactions() {
               txid: matchingTx.txid,
               height: matchingTx.height,
               timestamp: matchingTx.timestamp,
-              memo: decrypted.memo,
-              amount: decrypted.amount,
+              memo: memoText(decrypted),
+              zatoshis: decrypted.received_zatoshis,
+              amount: formatZatoshis(decrypted.received_zatoshis),
             });
           }
         } catch (err) {
-          // Failed to decrypt this TX (empty memo or change output)
+          // Failed to decrypt this TX (key matches none of its outputs)
         }
 
         processed++;
@@ -283,7 +285,7 @@ export function ScanMyTransactions() {
       await new Promise(resolve => setTimeout(resolve, 200));
 
       if (foundMessages.length === 0) {
-        setScanError(`Found ${matchingTxs.length} matching transactions but none had readable memos.`);
+        setScanError(`Found ${matchingTxs.length} matching transactions but none could be decrypted.`);
       } else {
         setScanResults(foundMessages);
         setTimeout(() => {
@@ -356,7 +358,7 @@ export function ScanMyTransactions() {
     const minScanTime = 1500; // 1.5 seconds minimum
 
     try {
-      const { decryptMemo } = await import('@/lib/wasm-loader');
+      const { decryptMemo, formatZatoshis, memoText } = await import('@/lib/wasm-loader');
 
       const apiUrl = getApiUrl();
 
@@ -462,8 +464,9 @@ export function ScanMyTransactions() {
               txid: tx.txid,
               height: tx.block_height,
               timestamp: tx.timestamp,
-              memo: decrypted.memo,
-              amount: decrypted.amount,
+              memo: memoText(decrypted),
+              zatoshis: decrypted.received_zatoshis,
+              amount: formatZatoshis(decrypted.received_zatoshis),
             });
           }
         } catch (err) {
@@ -801,12 +804,12 @@ export function ScanMyTransactions() {
                     <span className="text-muted">•</span>
 
                     {/* Amount */}
-                    {result.amount > 0 && (
+                    {result.zatoshis > 0 && (
                       <>
                         <div className="flex items-center gap-1.5">
                           <span className="text-muted font-bold uppercase tracking-wider">Amount:</span>
                           <span className="text-cipher-green font-mono font-semibold">
-                            +{result.amount.toString().replace(/\.?0+$/, '')} {CURRENCY}
+                            +{result.amount.replace(/\.?0+$/, '')} {CURRENCY}
                           </span>
                         </div>
                         <span className="text-muted">•</span>
@@ -35,7 +35,8 @@ export function SingleTxDecrypt({ prefillTxid }: { prefillTxid?: string | null }
   const [txid, setTxid] = useState('');
   const [viewingKey, setViewingKey] = useState('');
   const [memo, setMemo] = useState<string | null>(null);
-  const [amount, setAmount] = useState<number>(0);
+  const [zatoshis, setZatoshis] = useState<number>(0);
+  const [amount, setAmount] = useState<string>('');
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
   const [loadingStep, setLoadingStep] = useState(0);
@@ -90,7 +91,7 @@ export function SingleTxDecrypt({ prefillTxid }: { prefillTxid?: string | null }
     const minLoadTime = new Promise(resolve => setTimeout(resolve, 1800));
 
     try {
-      const { testWasm, detectKeyType, decryptMemoFromTxid } = await import('@/lib/wasm-loader');
+      const { testWasm, detectKeyType, decryptMemoFromTxid, formatZatoshis, memoText } = await import('@/lib/wasm-loader');
       const testResult = await testWasm();
       const keyType = await detectKeyType(sanitizedViewingKey);
       const result = await decryptMemoFromTxid(sanitizedTxid, sanitizedViewingKey);
@@ -98,8 +99,15 @@ export function SingleTxDecrypt({ prefillTxid }: { prefillTxid?: string | null }
       // Wait for minimum time
       await minLoadTime;
       clearInterval(stepInterval);
-      setMemo(result.memo);
-      setAmount(result.amount);
+      const text = memoText(result);
+      if (!text) {
+        throw new Error(
+          `No text memo in this transaction. Your viewing key matches ${result.outputs.length} output(s) totaling ${result.total_amount.replace(/\.?0+$/, '')} ZEC, but none include a memo.`
+        );
+      }
+      setMemo(text);
+      setZatoshis(result.received_zatoshis);
+      setAmount(formatZatoshis(result.received_zatoshis));
     } catch (err: any) {
       await minLoadTime;
       clearInterval(stepInterval);
@@ -112,7 +120,8 @@ export function SingleTxDecrypt({ prefillTxid }: { prefillTxid?: string | null }
 
   const reset = () => {
     setMemo(null);
-    setAmount(0);
+    setZatoshis(0);
+    setAmount('');
     setError(null);
     setTxid('');
     setViewingKey('');
@@ -304,11 +313,11 @@ export function SingleTxDecrypt({ prefillTxid }: { prefillTxid?: string | null }
                 <p className="text-[10px] sm:text-xs text-cipher-cyan mb-2">[ DECRYPTED OUTPUT ]</p>
 
                 {/* Amount */}
-                {amount > 0 && (
+                {zatoshis > 0 && (
                   <div className="mb-3 sm:mb-4">
                     <span className="text-xs sm:text-sm text-gray-400">Amount: </span>
                     <span className="text-lg sm:text-xl font-bold text-cipher-green font-mono">
-                      +{amount.toString().replace(/\.?0+$/, '')} ZEC
+                      +{amount.replace(/\.?0+$/, '')} ZEC
                     </span>
                   </div>
                 )}
@@ -7,8 +7,37 @@ let wasmModule: any = null;
 let wasmInitialized = false;
 
 export interface DecryptedOutput {
-  memo: string;
-  amount: number; // Amount in ZEC
+  memo: string; // Empty if the output has no text memo
+  zatoshis: number; // Exact value, use for comparisons and math
+  amount: string; // Amount in ZEC with 8 decimals, display only
+  action_index?: number; // Orchard action this output came from
+  commitment?: string; // Note commitment (cmx), only when requested
+}
+
+// decrypt_memo returns every output the key decrypts, plus totals
+export interface DecryptedTransaction {
+  outputs: DecryptedOutput[];
+  total_zatoshis: number;
+  total_amount: string;
+  received_zatoshis: number; // External scope
+  change_zatoshis: number; // Internal scope
+  filtered_outputs: number; // Dropped as dust, not in the totals
+  skipped_actions?: string[]; // Malformed actions, as "#index: reason"
+}
+
+// batch_filter_compact_outputs returns {matches, failures}
+export interface BatchMatch {
+  index: number; // Position in the input array
+  txid: string;
+  height: number;
+  scope: string; // "External" (received) or "Internal" (change)
+}
+
+export interface BatchFailure {
+  index: number;
+  txid: string | null; // null if the entry had no readable txid
+  height: number | null;
+  reason: string;
 }
 
 export interface UnifiedAddressComponents {
@@ -112,21 +141,58 @@ function patchBranchId(hex: string): string {
 }
 
 /**
- * Decrypt a memo from a transaction
- * @returns DecryptedOutput with memo and amount
+ * Format zatoshis as ZEC with 8 decimals, without going through floats
+ * Keep in sync with formatZatoshis in packages/zcash-decoder (the app doesn't depend on it)
+ */
+export function formatZatoshis(zatoshis: number): string {
+  const whole = Math.floor(zatoshis / 100_000_000);
+  const frac = zatoshis % 100_000_000;
+  return `${whole}.${String(frac).padStart(8, '0')}`;
+}
+
+/**
+ * Older WASM builds return a single {memo, amount} output with a float amount.
+ * Wrap it in the {outputs, ...} shape so callers only handle one format.
  */
-export async function decryptMemo(txHex: string, viewingKey: string): Promise<DecryptedOutput> {
+function normalizeTransaction(result: any): DecryptedTransaction {
+  if (Array.isArray(result.outputs)) return result;
+
+  const zatoshis = Math.round(result.amount * 100_000_000);
+  const amount = formatZatoshis(zatoshis);
+  return {
+    outputs: [{ memo: result.memo, zatoshis, amount }],
+    total_zatoshis: zatoshis,
+    total_amount: amount,
+    received_zatoshis: zatoshis,
+    change_zatoshis: 0,
+    filtered_outputs: 0,
+  };
+}
+
+/**
+ * Text memos of a decrypted transaction, joined for display
+ */
+export function memoText(tx: DecryptedTransaction): string {
+  return tx.outputs.map(output => output.memo).filter(Boolean).join('\n\n');
+}
+
+/**
+ * Decrypt every output of a transaction the viewing key can see
+ * @returns DecryptedTransaction with each output's memo and amount, plus totals
+ */
+export async function decryptMemo(txHex: string, viewingKey: string): Promise<DecryptedTransaction> {
   const wasm = await loadWasm();
   const patchedHex = patchBranchId(txHex);
 
   try {
     const result = wasm.decrypt_memo(patchedHex, viewingKey);
-    return JSON.parse(result);
+    return normalizeTransaction(JSON.parse(result));
   } catch (err: any) {
     const msg = err?.message || err?.toString() || '';
+    // Outputs without a memo are returned, so this now only means no output matched
     if (msg.includes('No memo found')) {
       throw new Error(
-        'No text memo in this transaction. The transaction may match your key but contains no memo (most transfers don\'t include one), or the viewing key doesn\'t match any outputs.'
+        'The viewing key doesn\'t match any outputs in this transaction. Check that the transaction ID and viewing key belong to the same wallet.'
       );
     }
     throw err;
@@ -134,10 +200,10 @@ export async function decryptMemo(txHex: string, viewingKey: string): Promise<De
 }
 
 /**
- * Decrypt a memo from a transaction ID (fetches raw hex first)
- * @returns DecryptedOutput with memo and amount
+ * Decrypt a transaction by ID (fetches raw hex first)
+ * @returns DecryptedTransaction with each output's memo and amount, plus totals
  */
-export async function decryptMemoFromTxid(txid: string, viewingKey: string): Promise<DecryptedOutput> {
+export async function decryptMemoFromTxid(txid: string, viewingKey: string): Promise<DecryptedTransaction> {
   // Use the correct API based on network
   const apiBaseUrl = getApiUrl();
   const apiUrl = `${apiBaseUrl}/api/tx/${txid}/raw`;
@@ -232,10 +298,16 @@ export async function filterCompactOutputsBatch(
     const startTime = Date.now();
 
     const matchesJson = wasm.batch_filter_compact_outputs(outputsJson, viewingKey);
-    const matches = JSON.parse(matchesJson);
+    const result = JSON.parse(matchesJson);
+    // Older WASM builds return a bare array of matches
+    const matches: BatchMatch[] = Array.isArray(result) ? result : result.matches;
+    const failures: BatchFailure[] = Array.isArray(result) ? [] : result.failures;
 
     const elapsed = Date.now() - startTime;
     console.log(`✅ [BATCH FILTER] Chunk filtered in ${elapsed}ms! Found ${matches.length} new matches`);
+    if (failures.length > 0) {
+      console.warn(`⚠️ [BATCH FILTER] Skipped ${failures.length} malformed outputs`, failures);
+    }
 
     // Convert matches to TXIDs (deduplicate)
     for (const match of matches) {
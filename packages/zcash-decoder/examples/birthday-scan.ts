@@ -84,8 +84,10 @@ async function main() {
         const result = await wasm.decryptMemo(rawHex, viewingKey);
 
         console.log(`\n  TXID: ${match.txid.slice(0, 16)}...`);
-        console.log(`  Amount: ${result.amount} ZEC`);
-        console.log(`  Memo: ${result.memo}`);
+        console.log(`  Amount: ${result.total_amount} ZEC`);
+        for (const output of result.outputs) {
+          console.log(`  Memo: ${output.memo}`);
+        }
       } catch (error) {
         console.error(`  ❌ Failed to decrypt ${match.txid}:`, error);
       }
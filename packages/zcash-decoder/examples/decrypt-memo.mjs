@@ -101,8 +101,10 @@ async function main() {
     console.log('━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━');
     console.log('✅ Decryption successful!');
     console.log('━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━');
-    console.log('📝 Memo:', result.memo || '(empty)');
-    console.log('💰 Amount:', result.amount, 'ZEC');
+    for (const output of result.outputs) {
+      console.log('📝 Memo:', output.memo || '(empty)');
+      console.log('💰 Amount:', output.amount, 'ZEC');
+    }
     console.log('━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n');
   } catch (e) {
     console.log('❌ Decryption failed:', e.message);
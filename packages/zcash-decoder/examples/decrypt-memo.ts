@@ -22,8 +22,10 @@ async function main() {
 
     console.log('✅ Decryption successful!\n');
     console.log('═══════════════════════════════════════');
-    console.log('Memo:', result.memo);
-    console.log('Amount:', result.amount, 'ZEC');
+    for (const output of result.outputs) {
+      console.log('Memo:', output.memo);
+      console.log('Amount:', output.amount, 'ZEC');
+    }
     console.log('═══════════════════════════════════════');
   } catch (error) {
     console.error('❌ Decryption failed:', error);
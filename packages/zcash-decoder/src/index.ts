@@ -28,7 +28,8 @@
 export * from './types.js';
 
 import type {
-  DecryptedOutput,
+  BatchMatch,
+  DecryptedTransaction,
   CompactBlock,
   MatchingTransaction,
   FilterProgress,
@@ -44,6 +45,16 @@ interface WASMModule {
   detect_key_type: (viewingKey: string) => string;
 }
 
+/**
+ * Format zatoshis as ZEC with 8 decimals, without going through floats
+ * Matches the WASM's own `amount` strings (and formatZatoshis in the CipherScan app)
+ */
+export function formatZatoshis(zatoshis: number): string {
+  const whole = Math.floor(zatoshis / 100_000_000);
+  const frac = zatoshis % 100_000_000;
+  return `${whole}.${String(frac).padStart(8, '0')}`;
+}
+
 /**
  * Main ZcashWASM class for client-side Zcash operations
  */
@@ -96,7 +107,7 @@ export class ZcashWASM {
    *
    * @param txHex - Raw transaction hex
    * @param viewingKey - Unified Full Viewing Key (UFVK)
-   * @returns Promise<DecryptedOutput> Decrypted memo and amount
+   * @returns Promise<DecryptedTransaction> Every decrypted output, plus totals
    *
    * @example
    * ```typescript
@@ -104,11 +115,13 @@ export class ZcashWASM {
    *   '0400008085202f89...',
    *   'uviewtest1...'
    * );
-   * console.log('Memo:', result.memo);
-   * console.log('Amount:', result.amount, 'ZEC');
+   * for (const output of result.outputs) {
+   *   console.log('Memo:', output.memo);
+   *   console.log('Amount:', output.amount, 'ZEC');
+   * }
    * ```
    */
-  async decryptMemo(txHex: string, viewingKey: string): Promise<DecryptedOutput> {
+  async decryptMemo(txHex: string, viewingKey: string): Promise<DecryptedTransaction> {
     if (!this.wasmModule) {
       throw new Error('WASM module not initialized. Call ZcashWASM.init() first.');
     }
@@ -116,12 +129,31 @@ export class ZcashWASM {
     try {
       const patchedHex = ZcashWASM.patchBranchId(txHex);
       const resultJson = this.wasmModule.decrypt_memo(patchedHex, viewingKey);
-      return JSON.parse(resultJson);
+      return ZcashWASM.normalizeTransaction(JSON.parse(resultJson));
     } catch (error) {
       throw new Error(`Failed to decrypt memo: ${error}`);
     }
   }
 
+  /**
+   * Older WASM builds return a single `{memo, amount}` output with a float
+   * amount. Wrap it in the `{outputs, ...}` shape so callers see one format.
+   */
+  private static normalizeTransaction(result: any): DecryptedTransaction {
+    if (Array.isArray(result.outputs)) return result;
+
+    const zatoshis = Math.round(result.amount * 100_000_000);
+    const amount = formatZatoshis(zatoshis);
+    return {
+      outputs: [{ memo: result.memo, zatoshis, amount }],
+      total_zatoshis: zatoshis,
+      total_amount: amount,
+      received_zatoshis: zatoshis,
+      change_zatoshis: 0,
+      filtered_outputs: 0,
+    };
+  }
+
   /**
    * Patch unknown consensus branch IDs to NU5 for parsing.
    * NU6/NU6.1/NU6.2 use the same v5 tx format — only proof rules changed.
@@ -220,7 +252,9 @@ export class ZcashWASM {
         // Call WASM batch filter
         const outputsJson = JSON.stringify(allOutputs);
         const matchesJson = this.wasmModule.batch_filter_compact_outputs(outputsJson, viewingKey);
-        const matches = JSON.parse(matchesJson);
+        const result = JSON.parse(matchesJson);
+        // Older WASM builds return a bare array of matches
+        const matches: BatchMatch[] = Array.isArray(result) ? result : result.matches;
 
         // Map indices back to transactions
         for (const match of matches) {
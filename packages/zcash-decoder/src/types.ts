@@ -4,8 +4,34 @@
 export interface DecryptedOutput {
   /** The decrypted memo text */
   memo: string;
-  /** Amount in ZEC */
-  amount: number;
+  /** Exact value in zatoshis (use for comparisons and math) */
+  zatoshis: number;
+  /** Amount in ZEC as a fixed 8-decimal string (display only) */
+  amount: string;
+  /** Index of the Orchard action this output came from */
+  action_index?: number;
+  /** Note commitment (cmx) as hex, only when requested */
+  commitment?: string;
+}
+
+/**
+ * Every output a viewing key decrypts in one transaction, with totals
+ */
+export interface DecryptedTransaction {
+  /** Decrypted outputs; memo is empty when the output has no text memo */
+  outputs: DecryptedOutput[];
+  /** Sum of all outputs in zatoshis */
+  total_zatoshis: number;
+  /** total_zatoshis in ZEC as a fixed 8-decimal string (display only) */
+  total_amount: string;
+  /** Outputs sent to the key's external addresses (received funds) */
+  received_zatoshis: number;
+  /** Outputs sent to the key's internal addresses (change) */
+  change_zatoshis: number;
+  /** Outputs dropped by a dust threshold (not included in the totals) */
+  filtered_outputs: number;
+  /** Malformed actions that were skipped, as "#index: reason" */
+  skipped_actions?: string[];
 }
 
 /**
@@ -46,6 +72,29 @@ export interface CompactOutput {
   ciphertext: string;
 }
 
+/**
+ * A matching output from batch_filter_compact_outputs
+ */
+export interface BatchMatch {
+  /** Position in the input array */
+  index: number;
+  txid: string;
+  height: number;
+  /** "External" (received) or "Internal" (change) */
+  scope: string;
+}
+
+/**
+ * An input entry batch_filter_compact_outputs couldn't parse (skipped)
+ */
+export interface BatchFailure {
+  index: number;
+  /** null if the entry had no readable txid */
+  txid: string | null;
+  height: number | null;
+  reason: string;
+}
+
 /**
  * Matching transaction from compact block filtering
  */
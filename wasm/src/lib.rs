@@ -1,16 +1,25 @@
 use wasm_bindgen::prelude::*;
 
 // 🎯 OFFICIAL 3-CRATE SOLUTION (zcash_primitives 0.25 + orchard 0.11)
-use zcash_note_encryption::{try_note_decryption, try_compact_note_decryption, batch};
+use zcash_note_encryption::{try_note_decryption, try_compact_note_decryption, batch, Domain, EphemeralKeyBytes};
 use orchard::{
     keys::{FullViewingKey, Scope, PreparedIncomingViewingKey},
     note_encryption::{OrchardDomain, CompactAction},
     note::ExtractedNoteCommitment,
 };
+use sapling_crypto::{
+    keys::PreparedIncomingViewingKey as SaplingPreparedIncomingViewingKey,
+    note_encryption::{try_sapling_compact_note_decryption, CompactOutputDescription, Zip212Enforcement},
+    zip32::DiversifiableFullViewingKey,
+};
 use zcash_address::unified::{Container, Encoding, Fvk, Ufvk, Address as UnifiedAddress, Receiver};
 
 // Use zcash_primitives for transaction parsing
-use zcash_primitives::transaction::Transaction;
+use zcash_primitives::transaction::{components::sapling::zip212_enforcement, Transaction, TxVersion};
+use zcash_protocol::consensus::{BlockHeight, BranchId, NetworkType, MAIN_NETWORK, TEST_NETWORK};
+use zcash_protocol::value::MAX_MONEY;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
 use std::io::Cursor;
 
 // For JSON serialization
@@ -19,7 +28,24 @@ use serde::{Serialize, Deserialize};
 #[derive(Serialize, Deserialize)]
 pub struct DecryptedOutput {
     pub memo: String,
-    pub amount: f64, // Amount in ZEC
+    pub zatoshis: u64,
+    pub amount: String, // Amount in ZEC, always 8 decimal places
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action_index: Option<usize>, // Orchard action this came from (absent for compact outputs)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commitment: Option<String>, // Note commitment (cmx) as hex, opt-in
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DecryptedTransaction {
+    pub outputs: Vec<DecryptedOutput>,
+    pub total_zatoshis: u64,
+    pub total_amount: String, // Total in ZEC, always 8 decimal places
+    pub received_zatoshis: u64, // External scope
+    pub change_zatoshis: u64, // Internal scope
+    pub filtered_outputs: usize, // Outputs dropped by min_zatoshis (excluded from totals)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_actions: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -32,6 +58,26 @@ pub struct UnifiedAddressComponents {
     pub sapling_address: Option<String>,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct ViewingKeyCapabilities {
+    pub orchard: bool,
+    pub sapling: bool,
+    pub transparent: bool,
+    pub network: String,
+}
+
+/// Raw fields of a single Orchard action, hex-encoded
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrchardActionFields {
+    pub nullifier: String,
+    pub cmx: String,
+    pub ephemeral_key: String,
+    pub enc_ciphertext: String,
+    pub out_ciphertext: String,
+    pub cv: String,
+}
+
 #[wasm_bindgen(start)]
 pub fn main() {
     console_error_panic_hook::set_once();
@@ -112,11 +158,57 @@ pub fn decode_unified_address(ua_string: &str) -> Result<String, String> {
         .map_err(|e| format!("JSON serialization failed: {:?}", e))
 }
 
-/// Orchard memo decryption - The Official Way™
-#[wasm_bindgen]
-pub fn decrypt_memo(tx_hex: &str, viewing_key: &str) -> Result<String, String> {
+/// Format a zatoshi amount as ZEC with exactly 8 decimal places
+/// Uses integer arithmetic so the result is always exact (no float artifacts)
+fn format_zec(zatoshis: u64) -> String {
+    format!("{}.{:08}", zatoshis / 100_000_000, zatoshis % 100_000_000)
+}
+
+/// Extract the text of a decrypted memo
+/// Returns an empty string for "no memo" (0xF6), binary memos and blank text
+fn memo_text(memo_bytes: &[u8]) -> String {
+    let memo_len = memo_bytes.iter().position(|&b| b == 0).unwrap_or(memo_bytes.len());
+
+    match std::str::from_utf8(&memo_bytes[..memo_len]) {
+        Ok(text) if !text.trim().is_empty() => text.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Encode an Orchard recipient as a unified address for the given network
+/// Orchard has no standalone address format, so this is a UA with a single
+/// Orchard receiver (u1... on mainnet, utest1... on testnet)
+fn encode_orchard_address(recipient: &orchard::Address, network: NetworkType) -> Result<String, String> {
+    let ua = UnifiedAddress::try_from_items(vec![Receiver::Orchard(recipient.to_raw_address_bytes())])
+        .map_err(|e| format!("Failed to build unified address: {:?}", e))?;
+
+    Ok(ua.encode(&network))
+}
+
+/// Parse a raw transaction from hex
+fn parse_transaction(tx_hex: &str) -> Result<Transaction, String> {
+    let tx_bytes = hex::decode(tx_hex)
+        .map_err(|e| format!("Hex decode failed: {:?}", e))?;
+
+    let mut cursor = Cursor::new(&tx_bytes[..]);
+    Transaction::read(&mut cursor, BranchId::Nu5)
+        .map_err(|e| format!("TX parse: {:?}", e))
+}
+
+/// Parse a UFVK and prepare its Orchard incoming viewing keys
+/// Returns `(external, internal)`. Preparing an IVK is the expensive part of
+/// key setup, so batch callers should do this once and reuse the result
+fn prepare_ivks(viewing_key: &str) -> Result<(PreparedIncomingViewingKey, PreparedIncomingViewingKey), String> {
+    let (_network, ivk_external, ivk_internal) = prepare_ivks_with_network(viewing_key)?;
+    Ok((ivk_external, ivk_internal))
+}
+
+/// Same as prepare_ivks, but also returns the network the UFVK was encoded for
+fn prepare_ivks_with_network(
+    viewing_key: &str,
+) -> Result<(NetworkType, PreparedIncomingViewingKey, PreparedIncomingViewingKey), String> {
     // Step 1: Parse UFVK
-    let (_network, ufvk) = Ufvk::decode(viewing_key)
+    let (network, ufvk) = Ufvk::decode(viewing_key)
         .map_err(|e| format!("UFVK decode failed: {:?}", e))?;
 
     // Step 2: Extract Orchard FVK
@@ -131,157 +223,391 @@ pub fn decrypt_memo(tx_hex: &str, viewing_key: &str) -> Result<String, String> {
     let fvk = FullViewingKey::from_bytes(&orchard_fvk_bytes)
         .ok_or("FVK parse failed")?;
 
-    // Step 4: Parse transaction with zcash_primitives
-    let tx_bytes = hex::decode(tx_hex)
-        .map_err(|e| format!("Hex decode failed: {:?}", e))?;
+    // Step 4: Prepare IVKs for both scopes
+    let ivk_external = fvk.to_ivk(Scope::External);
+    let ivk_internal = fvk.to_ivk(Scope::Internal);
 
-    let mut cursor = Cursor::new(&tx_bytes[..]);
-    let tx = Transaction::read(&mut cursor, zcash_protocol::consensus::BranchId::Nu5)
-        .map_err(|e| format!("TX parse: {:?}", e))?;
-
-    // Step 5: Get Orchard actions
-    let orchard_actions = match tx.orchard_bundle() {
-        Some(bundle) => {
-            let actions: Vec<_> = bundle.actions().iter().collect();
-            actions
-        },
-        None => {
-            return Err("No Orchard bundle in transaction".to_string());
-        }
-    };
+    Ok((
+        network,
+        PreparedIncomingViewingKey::new(&ivk_external),
+        PreparedIncomingViewingKey::new(&ivk_internal),
+    ))
+}
 
-    // Step 6: Try to decrypt all actions and collect valid outputs (memo + amount)
-    let mut found_outputs = Vec::new();
+/// Structural checks on an Orchard action before trial decryption
+/// Nullifier, cmx, rk and cv are already validated by the transaction parser,
+/// but the ephemeral key is kept as raw bytes and may not be a valid point
+fn validate_action<T>(action: &orchard::Action<T>) -> Result<(), String> {
+    let epk_bytes = EphemeralKeyBytes(action.encrypted_note().epk_bytes);
+    if OrchardDomain::epk(&epk_bytes).is_none() {
+        return Err("invalid ephemeral key".to_string());
+    }
 
-    for action in orchard_actions.iter() {
-        // Create domain for THIS specific action
-        let domain = OrchardDomain::for_action(*action);
+    Ok(())
+}
 
-        // Try both External and Internal scopes
-        for scope in [Scope::External, Scope::Internal] {
-            let ivk = fvk.to_ivk(scope);
-            let prepared_ivk = PreparedIncomingViewingKey::new(&ivk);
+/// A successfully decrypted Orchard action
+struct DecryptedAction {
+    action_index: usize,
+    scope: Scope,
+    note: orchard::Note,
+    recipient: orchard::Address,
+    memo: String,
+}
 
-            if let Some((note, _recipient, memo)) = try_note_decryption(&domain, &prepared_ivk, *action) {
-                let memo_bytes = memo.as_slice();
-                let memo_len = memo_bytes.iter().position(|&b| b == 0).unwrap_or(memo_bytes.len());
+/// Result of trial-decrypting a transaction's Orchard actions
+struct OrchardScan {
+    decrypted: Vec<DecryptedAction>,
+    skipped_actions: Vec<String>, // Malformed actions, as "#index: reason"
+    filtered_outputs: usize, // Decrypted but below min_zatoshis
+}
 
-                // Skip empty memos
-                if memo_len == 0 {
-                    continue;
-                }
+/// Trial-decrypt every Orchard action in a transaction with both scopes
+/// Outputs below `min_zatoshis` are counted in `filtered_outputs` but not returned
+fn decrypt_orchard_actions(
+    tx: &Transaction,
+    ivk_external: &PreparedIncomingViewingKey,
+    ivk_internal: &PreparedIncomingViewingKey,
+    min_zatoshis: Option<u64>,
+) -> Result<OrchardScan, String> {
+    let bundle = tx.orchard_bundle()
+        .ok_or("No Orchard bundle in transaction")?;
 
-                // Validate UTF-8 and skip invalid text
-                if let Ok(memo_text) = String::from_utf8(memo_bytes[..memo_len].to_vec()) {
-                    // Skip if memo is only whitespace
-                    if !memo_text.trim().is_empty() {
-                        // Extract amount from note (in zatoshis, convert to ZEC)
-                        let amount_zatoshis = note.value().inner();
-                        let amount_zec = amount_zatoshis as f64 / 100_000_000.0;
-
-                        found_outputs.push(DecryptedOutput {
-                            memo: memo_text,
-                            amount: amount_zec,
-                        });
-                    }
+    Ok(decrypt_actions(bundle.actions().iter(), ivk_external, ivk_internal, min_zatoshis))
+}
+
+/// Trial-decrypt a sequence of Orchard actions with both scopes
+/// Split out from decrypt_orchard_actions so it works on unauthorized actions too
+fn decrypt_actions<'a, T: 'a>(
+    actions: impl IntoIterator<Item = &'a orchard::Action<T>>,
+    ivk_external: &PreparedIncomingViewingKey,
+    ivk_internal: &PreparedIncomingViewingKey,
+    min_zatoshis: Option<u64>,
+) -> OrchardScan {
+    let mut decrypted = Vec::new();
+    let mut skipped_actions = Vec::new();
+    let mut filtered_outputs = 0;
+
+    for (action_index, action) in actions.into_iter().enumerate() {
+        // Skip (and record) structurally invalid actions instead of decrypting them
+        if let Err(reason) = validate_action(action) {
+            skipped_actions.push(format!("#{}: {}", action_index, reason));
+            continue;
+        }
+
+        // Create domain for THIS specific action
+        let domain = OrchardDomain::for_action(action);
+
+        // Try both External and Internal scopes
+        for (prepared_ivk, scope) in [(ivk_external, Scope::External), (ivk_internal, Scope::Internal)] {
+            if let Some((note, recipient, memo)) = try_note_decryption(&domain, prepared_ivk, action) {
+                // Skip outputs below the dust threshold
+                if min_zatoshis.is_some_and(|min| note.value().inner() < min) {
+                    filtered_outputs += 1;
+                    break;
                 }
+
+                decrypted.push(DecryptedAction {
+                    action_index,
+                    scope,
+                    note,
+                    recipient,
+                    memo: memo_text(memo.as_slice()),
+                });
+
+                // An action only decrypts under one scope
+                break;
             }
         }
     }
 
-    // Return the first valid output found as JSON
-    if let Some(output) = found_outputs.first() {
-        serde_json::to_string(output)
-            .map_err(|e| format!("JSON serialization failed: {:?}", e))
+    OrchardScan { decrypted, skipped_actions, filtered_outputs }
+}
+
+/// Error for a transaction where nothing decrypted, naming any skipped actions
+fn no_match_error(skipped_actions: &[String]) -> String {
+    if skipped_actions.is_empty() {
+        "No memo found or viewing key doesn't match any outputs.".to_string()
     } else {
-        Err("No memo found or viewing key doesn't match any outputs.".to_string())
+        format!(
+            "No memo found or viewing key doesn't match any outputs. Skipped malformed actions: {}",
+            skipped_actions.join(", ")
+        )
     }
 }
 
-/// Decrypt a compact block output (from Lightwalletd)
-/// This is MUCH faster than decrypt_memo because it doesn't need the full TX
+/// Orchard memo decryption - The Official Way™
+///
+/// Returns every output the key decrypts, plus `total_zatoshis` split into
+/// `received_zatoshis` (External scope) and `change_zatoshis` (Internal scope).
+/// Outputs without a text memo are included with an empty `memo`
+///
+/// If `min_zatoshis` is set, decrypted outputs below that value are omitted
+/// from the results (not flagged), so dust never reaches JS. The totals then
+/// cover only the returned outputs; `filtered_outputs` counts what was dropped.
+/// If the key matched but everything was dust, `outputs` is empty (not an error).
+/// From JS this is `bigint | undefined`: pass `10000n` or `BigInt(x)`, a plain
+/// number throws
+///
+/// If `include_commitment` is true, each output also carries the note's
+/// recomputed cmx so callers can match it against the on-chain action
 #[wasm_bindgen]
-pub fn decrypt_compact_output(
-    nullifier_hex: &str,
-    cmx_hex: &str,
-    ephemeral_key_hex: &str,
-    ciphertext_hex: &str,
+pub fn decrypt_memo(
+    tx_hex: &str,
     viewing_key: &str,
+    min_zatoshis: Option<u64>,
+    include_commitment: Option<bool>,
 ) -> Result<String, String> {
-    // Step 1: Parse UFVK
-    let (_network, ufvk) = Ufvk::decode(viewing_key)
-        .map_err(|e| format!("UFVK decode failed: {:?}", e))?;
+    let tx = parse_transaction(tx_hex)?;
+    let result = decrypt_transaction(&tx, viewing_key, min_zatoshis, include_commitment.unwrap_or(false))?;
 
-    // Step 2: Extract Orchard FVK
-    let orchard_fvk_bytes = ufvk.items().iter().find_map(|fvk| {
-        match fvk {
-            Fvk::Orchard(data) => Some(*data),
-            _ => None,
+    serde_json::to_string(&result)
+        .map_err(|e| format!("JSON serialization failed: {:?}", e))
+}
+
+/// Same as decrypt_memo, but returns the result as CBOR bytes
+/// Smaller and faster to produce than JSON for Rust/WASM consumers
+#[cfg(feature = "cbor")]
+#[wasm_bindgen]
+pub fn decrypt_memo_cbor(
+    tx_hex: &str,
+    viewing_key: &str,
+    min_zatoshis: Option<u64>,
+    include_commitment: Option<bool>,
+) -> Result<Vec<u8>, String> {
+    let tx = parse_transaction(tx_hex)?;
+    let result = decrypt_transaction(&tx, viewing_key, min_zatoshis, include_commitment.unwrap_or(false))?;
+
+    to_cbor(&result)
+}
+
+/// Serialize a result as CBOR
+#[cfg(feature = "cbor")]
+fn to_cbor<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(value, &mut bytes)
+        .map_err(|e| format!("CBOR serialization failed: {:?}", e))?;
+    Ok(bytes)
+}
+
+/// Decrypt all Orchard outputs of a transaction (shared by the JSON and CBOR entry points)
+fn decrypt_transaction(
+    tx: &Transaction,
+    viewing_key: &str,
+    min_zatoshis: Option<u64>,
+    include_commitment: bool,
+) -> Result<DecryptedTransaction, String> {
+    // Step 1: Prepare Orchard IVKs (both scopes) from the UFVK
+    let (ivk_external, ivk_internal) = prepare_ivks(viewing_key)?;
+
+    // Step 2: Try to decrypt all actions
+    let scan = decrypt_orchard_actions(tx, &ivk_external, &ivk_internal, min_zatoshis)?;
+
+    // Only a real non-match is an error; a match that was all dust is an empty result
+    if scan.decrypted.is_empty() && scan.filtered_outputs == 0 {
+        return Err(no_match_error(&scan.skipped_actions));
+    }
+
+    // Step 3: Collect outputs (memo + amount) and their totals
+    summarize_scan(scan, include_commitment)
+}
+
+/// Add a note value to a running total, rejecting anything above MAX_MONEY
+/// Trial decryption checks no proofs, so a crafted transaction can carry any
+/// u64 note value; without this the totals would overflow or wrap
+fn add_zatoshis(total: u64, amount: u64) -> Result<u64, String> {
+    total.checked_add(amount)
+        .filter(|sum| *sum <= MAX_MONEY)
+        .ok_or_else(|| "Decrypted note values exceed the maximum ZEC supply".to_string())
+}
+
+/// Turn decrypted actions into serializable outputs plus their totals
+fn summarize_scan(scan: OrchardScan, include_commitment: bool) -> Result<DecryptedTransaction, String> {
+    let OrchardScan { decrypted, skipped_actions, filtered_outputs } = scan;
+    let mut found_outputs = Vec::new();
+    let mut received_zatoshis: u64 = 0;
+    let mut change_zatoshis: u64 = 0;
+
+    for action in decrypted {
+        // Extract amount from note (in zatoshis)
+        let amount_zatoshis = action.note.value().inner();
+
+        // External scope = received from someone else, Internal = our own change
+        match action.scope {
+            Scope::External => received_zatoshis = add_zatoshis(received_zatoshis, amount_zatoshis)?,
+            Scope::Internal => change_zatoshis = add_zatoshis(change_zatoshis, amount_zatoshis)?,
         }
-    }).ok_or("No Orchard FVK found in UFVK")?;
 
-    // Step 3: Parse FullViewingKey
-    let fvk = FullViewingKey::from_bytes(&orchard_fvk_bytes)
-        .ok_or("FVK parse failed")?;
+        // Recompute the note commitment only when asked for
+        let commitment = include_commitment.then(|| {
+            hex::encode(ExtractedNoteCommitment::from(action.note.commitment()).to_bytes())
+        });
+
+        found_outputs.push(DecryptedOutput {
+            memo: action.memo,
+            zatoshis: amount_zatoshis,
+            amount: format_zec(amount_zatoshis),
+            action_index: Some(action.action_index),
+            commitment,
+        });
+    }
+
+    // Return all decrypted outputs and their totals
+    let total_zatoshis = add_zatoshis(received_zatoshis, change_zatoshis)?;
+    Ok(DecryptedTransaction {
+        outputs: found_outputs,
+        total_zatoshis,
+        total_amount: format_zec(total_zatoshis),
+        received_zatoshis,
+        change_zatoshis,
+        filtered_outputs,
+        skipped_actions,
+    })
+}
+
+/// Decrypt all outputs and group them by recipient address
+/// Returns a JSON object keyed by the recipient's Orchard-only unified address,
+/// each mapping to an array of `{zatoshis, memo, action_index}`
+#[wasm_bindgen]
+pub fn decrypt_memo_grouped(tx_hex: &str, viewing_key: &str) -> Result<String, String> {
+    let tx = parse_transaction(tx_hex)?;
+    grouped_outputs(&tx, viewing_key)
+}
+
+fn grouped_outputs(tx: &Transaction, viewing_key: &str) -> Result<String, String> {
+    #[derive(serde::Serialize)]
+    struct GroupedOutput {
+        zatoshis: u64,
+        memo: String,
+        action_index: usize,
+    }
 
-    // Step 4: Parse compact output data
-    let nullifier_bytes = hex::decode(nullifier_hex)
-        .map_err(|e| format!("Nullifier hex decode failed: {:?}", e))?;
-    let cmx_bytes = hex::decode(cmx_hex)
-        .map_err(|e| format!("CMX hex decode failed: {:?}", e))?;
-    let ephemeral_key_bytes = hex::decode(ephemeral_key_hex)
-        .map_err(|e| format!("Ephemeral key hex decode failed: {:?}", e))?;
-    let ciphertext_bytes = hex::decode(ciphertext_hex)
-        .map_err(|e| format!("Ciphertext hex decode failed: {:?}", e))?;
-
-    // Step 5: Convert to proper types
-    let nullifier_array: [u8; 32] = nullifier_bytes.try_into().map_err(|_| "Invalid nullifier length")?;
-    let nullifier = orchard::note::Nullifier::from_bytes(&nullifier_array)
+    // Step 1: Prepare Orchard IVKs (both scopes) and get the key's network
+    let (network, ivk_external, ivk_internal) = prepare_ivks_with_network(viewing_key)?;
+
+    // Step 2: Try to decrypt all actions
+    let OrchardScan { decrypted, skipped_actions, .. } =
+        decrypt_orchard_actions(tx, &ivk_external, &ivk_internal, None)?;
+
+    if decrypted.is_empty() {
+        return Err(no_match_error(&skipped_actions));
+    }
+
+    // Step 3: Group by encoded recipient address
+    let mut grouped: BTreeMap<String, Vec<GroupedOutput>> = BTreeMap::new();
+
+    for action in decrypted {
+        let address = encode_orchard_address(&action.recipient, network)?;
+
+        grouped.entry(address).or_default().push(GroupedOutput {
+            zatoshis: action.note.value().inner(),
+            memo: action.memo,
+            action_index: action.action_index,
+        });
+    }
+
+    serde_json::to_string(&grouped)
+        .map_err(|e| format!("JSON serialization failed: {:?}", e))
+}
+
+/// Decode the hex fields of an Orchard compact action and check their lengths
+fn parse_compact_action(
+    nullifier_hex: &str,
+    cmx_hex: &str,
+    ephemeral_key_hex: &str,
+    ciphertext_hex: &str,
+) -> Result<CompactAction, String> {
+    let nullifier = orchard::note::Nullifier::from_bytes(&hex_array(nullifier_hex, "Nullifier")?)
         .into_option()
         .ok_or("Invalid nullifier")?;
-
-    let cmx_array: [u8; 32] = cmx_bytes.try_into().map_err(|_| "Invalid CMX length")?;
-    let cmx = ExtractedNoteCommitment::from_bytes(&cmx_array)
+    let cmx = ExtractedNoteCommitment::from_bytes(&hex_array(cmx_hex, "CMX")?)
         .into_option()
         .ok_or("Invalid CMX")?;
-
-    let ephemeral_key_array: [u8; 32] = ephemeral_key_bytes.try_into().map_err(|_| "Invalid ephemeral key length")?;
+    let ephemeral_key: [u8; 32] = hex_array(ephemeral_key_hex, "Ephemeral key")?;
 
     // Ciphertext should be 52 bytes for compact format
-    if ciphertext_bytes.len() != 52 {
-        return Err(format!("Invalid ciphertext length: expected 52, got {}", ciphertext_bytes.len()));
-    }
-    let ciphertext: [u8; 52] = ciphertext_bytes.try_into().unwrap();
+    let ciphertext: [u8; 52] = hex_array(ciphertext_hex, "Ciphertext")?;
 
-    // Step 6: Create CompactAction with real nullifier
-    let compact_action = CompactAction::from_parts(
+    Ok(CompactAction::from_parts(
         nullifier,
         cmx,
-        ephemeral_key_array.into(),
+        ephemeral_key.into(),
         ciphertext,
-    );
+    ))
+}
+
+/// Decode the hex fields of a Sapling compact output and check their lengths
+fn parse_compact_sapling_output(
+    cmu_hex: &str,
+    ephemeral_key_hex: &str,
+    ciphertext_hex: &str,
+) -> Result<CompactOutputDescription, String> {
+    let cmu = sapling_crypto::note::ExtractedNoteCommitment::from_bytes(&hex_array(cmu_hex, "CMU")?)
+        .into_option()
+        .ok_or("Invalid CMU")?;
+    let ephemeral_key: [u8; 32] = hex_array(ephemeral_key_hex, "Ephemeral key")?;
 
-    // Step 7: Try to decrypt with both External and Internal scopes
-    for scope in [Scope::External, Scope::Internal] {
-        let ivk = fvk.to_ivk(scope);
-        let prepared_ivk = PreparedIncomingViewingKey::new(&ivk);
+    // Ciphertext should be 52 bytes for compact format
+    let enc_ciphertext: [u8; 52] = hex_array(ciphertext_hex, "Ciphertext")?;
 
+    Ok(CompactOutputDescription {
+        ephemeral_key: EphemeralKeyBytes(ephemeral_key),
+        cmu,
+        enc_ciphertext,
+    })
+}
+
+/// Decode a hex field into a fixed-size array
+/// `field` names the field in error messages, e.g. "Invalid CMX length: expected 32, got 31"
+fn hex_array<const N: usize>(field_hex: &str, field: &str) -> Result<[u8; N], String> {
+    let bytes = hex::decode(field_hex)
+        .map_err(|e| format!("{} hex decode failed: {:?}", field, e))?;
+    let len = bytes.len();
+
+    bytes.try_into()
+        .map_err(|_| format!("Invalid {} length: expected {}, got {}", field, N, len))
+}
+
+/// Decrypt a compact block output (from Lightwalletd)
+/// This is MUCH faster than decrypt_memo because it doesn't need the full TX
+///
+/// Orchard needs the action's real nullifier: it is the rho of the output note
+/// and is required to recompute the note commitment. For Sapling compact
+/// outputs (which carry no nullifier) use decrypt_compact_sapling_output
+#[wasm_bindgen]
+pub fn decrypt_compact_output(
+    nullifier_hex: &str,
+    cmx_hex: &str,
+    ephemeral_key_hex: &str,
+    ciphertext_hex: &str,
+    viewing_key: &str,
+) -> Result<String, String> {
+    // Step 1: Prepare Orchard IVKs (both scopes) from the UFVK
+    let (ivk_external, ivk_internal) = prepare_ivks(viewing_key)?;
+
+    // Step 2: Parse compact output data into a CompactAction with real nullifier
+    let compact_action = parse_compact_action(nullifier_hex, cmx_hex, ephemeral_key_hex, ciphertext_hex)?;
+
+    // Step 3: Try to decrypt with both External and Internal scopes
+    for prepared_ivk in [&ivk_external, &ivk_internal] {
         // Create domain for this compact action
         let domain = OrchardDomain::for_compact_action(&compact_action);
 
         // Try compact note decryption
-        if let Some((note, _recipient)) = try_compact_note_decryption(&domain, &prepared_ivk, &compact_action) {
+        if let Some((note, _recipient)) = try_compact_note_decryption(&domain, prepared_ivk, &compact_action) {
             // Compact decryption doesn't give us the memo directly
             // We need to extract it from the ciphertext manually
             // For now, we'll return a placeholder memo with the amount
 
-            // Extract amount from note (in zatoshis, convert to ZEC)
+            // Extract amount from note (in zatoshis)
             let amount_zatoshis = note.value().inner();
-            let amount_zec = amount_zatoshis as f64 / 100_000_000.0;
 
             let output = DecryptedOutput {
                 memo: "[Compact block - memo not available]".to_string(),
-                amount: amount_zec,
+                zatoshis: amount_zatoshis,
+                amount: format_zec(amount_zatoshis),
+                action_index: None,
+                commitment: None,
             };
 
             return serde_json::to_string(&output)
@@ -293,12 +619,56 @@ pub fn decrypt_compact_output(
 }
 
 /// Batch filter compact outputs (MUCH FASTER!)
-/// Takes JSON array of outputs and returns JSON array of matching indices
+/// Takes JSON array of outputs and returns JSON `{matches, failures}`:
+/// the matching indices, plus the index and reason for every entry that
+/// couldn't be parsed (those are skipped, the rest of the batch still runs)
 #[wasm_bindgen]
 pub fn batch_filter_compact_outputs(
     outputs_json: &str,
     viewing_key: &str,
 ) -> Result<String, String> {
+    let result = batch_filter(outputs_json, viewing_key)?;
+
+    serde_json::to_string(&result)
+        .map_err(|e| format!("JSON serialization failed: {:?}", e))
+}
+
+/// Same as batch_filter_compact_outputs, but returns the result as CBOR bytes
+#[cfg(feature = "cbor")]
+#[wasm_bindgen]
+pub fn batch_filter_compact_outputs_cbor(
+    outputs_json: &str,
+    viewing_key: &str,
+) -> Result<Vec<u8>, String> {
+    let result = batch_filter(outputs_json, viewing_key)?;
+
+    to_cbor(&result)
+}
+
+#[derive(Serialize)]
+struct BatchMatch {
+    index: usize,
+    txid: String,
+    height: u64,
+    scope: String,
+}
+
+#[derive(Serialize)]
+struct BatchFailure {
+    index: usize,
+    txid: Option<String>, // None if the entry had no readable txid
+    height: Option<u64>,
+    reason: String,
+}
+
+#[derive(Serialize)]
+struct BatchResult {
+    matches: Vec<BatchMatch>,
+    failures: Vec<BatchFailure>,
+}
+
+/// Batch-decrypt compact outputs (shared by the JSON and CBOR entry points)
+fn batch_filter(outputs_json: &str, viewing_key: &str) -> Result<BatchResult, String> {
     // Parse JSON input: array of {nullifier, cmx, ephemeralKey, ciphertext, txid, height}
     #[derive(serde::Deserialize)]
     struct CompactOutput {
@@ -310,105 +680,710 @@ pub fn batch_filter_compact_outputs(
         height: u64,
     }
 
-    let outputs: Vec<CompactOutput> = serde_json::from_str(outputs_json)
+    // Only the outer array has to be valid; each entry is checked on its own below
+    let entries: Vec<serde_json::Value> = serde_json::from_str(outputs_json)
         .map_err(|e| format!("Failed to parse outputs JSON: {:?}", e))?;
 
-    // Step 1: Parse UFVK ONCE
-    let (_network, ufvk) = Ufvk::decode(viewing_key)
+    // Step 1: Parse UFVK and prepare IVKs ONCE (both scopes)
+    // Index 0 is External, index 1 is Internal
+    let (ivk_external, ivk_internal) = prepare_ivks(viewing_key)?;
+    let prepared_ivks = vec![ivk_external, ivk_internal];
+
+    // Step 2: Parse all compact outputs
+    // Entries that fail to parse are recorded and left out of the batch
+    // instead of aborting it, so one corrupt output can't block a whole block
+    let mut parsed_outputs: Vec<(OrchardDomain, CompactAction)> = Vec::new();
+    let mut parsed_indices: Vec<(usize, CompactOutput)> = Vec::new();
+    let mut failures: Vec<BatchFailure> = Vec::new();
+
+    for (i, entry) in entries.into_iter().enumerate() {
+        // Best-effort txid/height so a failure can still be traced to its transaction
+        let txid = entry.get("txid").and_then(|v| v.as_str()).map(str::to_string);
+        let height = entry.get("height").and_then(|v| v.as_u64());
+
+        let parsed = serde_json::from_value::<CompactOutput>(entry)
+            .map_err(|e| format!("Invalid output entry: {}", e))
+            .and_then(|output| {
+                parse_compact_action(&output.nullifier, &output.cmx, &output.ephemeral_key, &output.ciphertext)
+                    .map(|compact_action| (output, compact_action))
+            });
+
+        match parsed {
+            Ok((output, compact_action)) => {
+                let domain = OrchardDomain::for_compact_action(&compact_action);
+                parsed_outputs.push((domain, compact_action));
+                parsed_indices.push((i, output));
+            },
+            Err(reason) => {
+                failures.push(BatchFailure { index: i, txid, height, reason });
+            }
+        }
+    }
+
+    // Step 3: BATCH DECRYPT ALL OUTPUTS AT ONCE!
+    let results = batch::try_compact_note_decryption(&prepared_ivks, &parsed_outputs);
+
+    // Step 4: Collect matching indices and their TXIDs
+    let matches: Vec<BatchMatch> = results.iter()
+        .zip(parsed_indices.iter())
+        .filter_map(|(result, (i, output))| {
+            result.as_ref().map(|((_note, _recipient), ivk_idx)| {
+                let scope_name = if *ivk_idx == 0 { "External" } else { "Internal" };
+                BatchMatch {
+                    index: *i,
+                    txid: output.txid.clone(),
+                    height: output.height,
+                    scope: scope_name.to_string(),
+                }
+            })
+        })
+        .collect();
+
+    Ok(BatchResult { matches, failures })
+}
+
+/// Return the raw fields of one Orchard action in a transaction
+/// Useful for building compact outputs (the first 52 bytes of encCiphertext)
+/// and for debugging field alignment
+#[wasm_bindgen]
+pub fn get_orchard_action(tx_hex: &str, action_index: usize) -> Result<String, String> {
+    let tx = parse_transaction(tx_hex)?;
+    orchard_action_fields(&tx, action_index)
+}
+
+fn orchard_action_fields(tx: &Transaction, action_index: usize) -> Result<String, String> {
+    let bundle = tx.orchard_bundle()
+        .ok_or("No Orchard bundle in transaction")?;
+
+    let action_count = bundle.actions().len();
+    let action = bundle.actions().get(action_index)
+        .ok_or_else(|| format!("Action index {} out of range (transaction has {} actions)", action_index, action_count))?;
+
+    let encrypted_note = action.encrypted_note();
+
+    let fields = OrchardActionFields {
+        nullifier: hex::encode(action.nullifier().to_bytes()),
+        cmx: hex::encode(action.cmx().to_bytes()),
+        ephemeral_key: hex::encode(encrypted_note.epk_bytes),
+        enc_ciphertext: hex::encode(encrypted_note.enc_ciphertext),
+        out_ciphertext: hex::encode(encrypted_note.out_ciphertext),
+        cv: hex::encode(action.cv_net().to_bytes()),
+    };
+
+    serde_json::to_string(&fields)
+        .map_err(|e| format!("JSON serialization failed: {:?}", e))
+}
+
+/// Return the shielded/transparent pools a transaction touches
+/// as a JSON array, e.g. ["transparent", "orchard"]
+#[wasm_bindgen]
+pub fn tx_pools(tx_hex: &str) -> Result<String, String> {
+    let tx = parse_transaction(tx_hex)?;
+    pools_of(&tx)
+}
+
+fn pools_of(tx: &Transaction) -> Result<String, String> {
+    let mut pools: Vec<&str> = Vec::new();
+
+    if tx.transparent_bundle().is_some_and(|b| !b.vin.is_empty() || !b.vout.is_empty()) {
+        pools.push("transparent");
+    }
+    if tx.sprout_bundle().is_some_and(|b| !b.joinsplits.is_empty()) {
+        pools.push("sprout");
+    }
+    if tx.sapling_bundle().is_some_and(|b| !b.shielded_spends().is_empty() || !b.shielded_outputs().is_empty()) {
+        pools.push("sapling");
+    }
+    if tx.orchard_bundle().is_some_and(|b| !b.actions().is_empty()) {
+        pools.push("orchard");
+    }
+
+    serde_json::to_string(&pools)
+        .map_err(|e| format!("JSON serialization failed: {:?}", e))
+}
+
+/// Decode the BIP34 block height from a coinbase scriptSig
+/// Heights 1-16 are pushed as OP_1..OP_16, larger ones as a little-endian number
+fn coinbase_height(script_sig: &[u8]) -> Option<u32> {
+    let first = *script_sig.first()?;
+    match first {
+        0x00 => Some(0),
+        0x51..=0x60 => Some(u32::from(first - 0x50)),
+        0x01..=0x04 => {
+            let len = first as usize;
+            let bytes = script_sig.get(1..1 + len)?;
+            let mut height_bytes = [0u8; 4];
+            height_bytes[..len].copy_from_slice(bytes);
+            Some(u32::from_le_bytes(height_bytes))
+        },
+        _ => None,
+    }
+}
+
+/// Infer whether a raw transaction belongs to mainnet or testnet
+///
+/// Only v5+ coinbase transactions can be classified: they carry both a block
+/// height and an explicit consensus branch ID, and the branch active at that
+/// height differs between networks. Everything else returns "unknown"
+///
+/// Pass the original hex, not the output of `patchBranchId` in
+/// lib/wasm-loader.ts: that rewrites NU6+ branch IDs to NU5, so a patched
+/// post-NU6 coinbase is read as NU5 and comes back wrong or "unknown"
+#[wasm_bindgen]
+pub fn detect_tx_network(tx_hex: &str) -> Result<String, String> {
+    let tx = parse_transaction(tx_hex)?;
+    Ok(network_of(&tx).to_string())
+}
+
+fn network_of(tx: &Transaction) -> &'static str {
+    // Pre-v5 transactions don't encode their branch ID
+    if !matches!(tx.version(), TxVersion::V5) {
+        return "unknown";
+    }
+
+    let height = tx.transparent_bundle()
+        .filter(|bundle| bundle.is_coinbase())
+        .and_then(|bundle| bundle.vin.first())
+        .and_then(|input| coinbase_height(&input.script_sig().0.0));
+
+    let height = match height {
+        Some(h) => BlockHeight::from_u32(h),
+        None => return "unknown",
+    };
+
+    let branch_id = tx.consensus_branch_id();
+    let matches_main = BranchId::for_height(&MAIN_NETWORK, height) == branch_id;
+    let matches_test = BranchId::for_height(&TEST_NETWORK, height) == branch_id;
+
+    match (matches_main, matches_test) {
+        (true, false) => "main",
+        (false, true) => "test",
+        _ => "unknown",
+    }
+}
+
+/// Parse a UFVK and prepare its Sapling incoming viewing keys
+/// Returns `(network, external, internal)`, mirroring prepare_ivks_with_network
+fn prepare_sapling_ivks(
+    viewing_key: &str,
+) -> Result<(NetworkType, SaplingPreparedIncomingViewingKey, SaplingPreparedIncomingViewingKey), String> {
+    // Step 1: Parse UFVK
+    let (network, ufvk) = Ufvk::decode(viewing_key)
         .map_err(|e| format!("UFVK decode failed: {:?}", e))?;
 
-    let orchard_fvk_bytes = ufvk.items().iter().find_map(|fvk| {
+    // Step 2: Extract Sapling FVK
+    let sapling_fvk_bytes = ufvk.items().iter().find_map(|fvk| {
         match fvk {
-            Fvk::Orchard(data) => Some(*data),
+            Fvk::Sapling(data) => Some(*data),
             _ => None,
         }
-    }).ok_or("No Orchard FVK found in UFVK")?;
+    }).ok_or("No Sapling FVK found in UFVK")?;
 
-    let fvk = FullViewingKey::from_bytes(&orchard_fvk_bytes)
-        .ok_or("FVK parse failed")?;
+    // Step 3: Parse DiversifiableFullViewingKey
+    let dfvk = DiversifiableFullViewingKey::from_bytes(&sapling_fvk_bytes)
+        .ok_or("Sapling FVK parse failed")?;
 
-    // Step 2: Prepare IVKs ONCE (both scopes)
-    let ivk_external = fvk.to_ivk(Scope::External);
-    let ivk_internal = fvk.to_ivk(Scope::Internal);
-    let prepared_ivks = vec![
-        PreparedIncomingViewingKey::new(&ivk_external),
-        PreparedIncomingViewingKey::new(&ivk_internal),
-    ];
+    // Step 4: Prepare IVKs for both scopes
+    Ok((
+        network,
+        SaplingPreparedIncomingViewingKey::new(&dfvk.to_ivk(Scope::External)),
+        SaplingPreparedIncomingViewingKey::new(&dfvk.to_ivk(Scope::Internal)),
+    ))
+}
 
-    // Step 3: Parse all compact outputs
-    let mut parsed_outputs: Vec<(OrchardDomain, CompactAction)> = Vec::new();
+/// Decrypt a Sapling compact block output (from Lightwalletd)
+/// Sapling compact outputs only carry cmu, ephemeralKey and the 52-byte
+/// ciphertext, so unlike Orchard no nullifier is needed
+///
+/// `height` is the output's block height. It selects the ZIP 212 rule for the
+/// key's network: pre-ZIP 212 (0x01) plaintexts are only accepted before the
+/// end of the Canopy grace period. Without a height, ZIP 212 is enforced, which
+/// is correct for every output mined after the grace period ended (2021)
+#[wasm_bindgen]
+pub fn decrypt_compact_sapling_output(
+    cmu_hex: &str,
+    ephemeral_key_hex: &str,
+    ciphertext_hex: &str,
+    viewing_key: &str,
+    height: Option<u32>,
+) -> Result<String, String> {
+    // Step 1: Prepare Sapling IVKs (both scopes) and get the key's network
+    let (network, ivk_external, ivk_internal) = prepare_sapling_ivks(viewing_key)?;
+
+    // Step 2: Parse compact output data
+    let compact_output = parse_compact_sapling_output(cmu_hex, ephemeral_key_hex, ciphertext_hex)?;
+
+    // Step 3: Pick the ZIP 212 rule for this height
+    let zip212_enforcement = match (height, network) {
+        (None, _) => Zip212Enforcement::On,
+        (Some(h), NetworkType::Main) => zip212_enforcement(&MAIN_NETWORK, BlockHeight::from_u32(h)),
+        (Some(h), _) => zip212_enforcement(&TEST_NETWORK, BlockHeight::from_u32(h)),
+    };
 
-    for output in &outputs {
-        // Decode hex
-        let nullifier_bytes = hex::decode(&output.nullifier)
-            .map_err(|e| format!("Nullifier hex decode failed: {:?}", e))?;
-        let cmx_bytes = hex::decode(&output.cmx)
-            .map_err(|e| format!("CMX hex decode failed: {:?}", e))?;
-        let ephemeral_key_bytes = hex::decode(&output.ephemeral_key)
-            .map_err(|e| format!("Ephemeral key hex decode failed: {:?}", e))?;
-        let ciphertext_bytes = hex::decode(&output.ciphertext)
-            .map_err(|e| format!("Ciphertext hex decode failed: {:?}", e))?;
-
-        // Convert to proper types
-        let nullifier_array: [u8; 32] = nullifier_bytes.try_into()
-            .map_err(|_| "Invalid nullifier length")?;
-        let nullifier = orchard::note::Nullifier::from_bytes(&nullifier_array)
-            .into_option()
-            .ok_or("Invalid nullifier")?;
-
-        let cmx_array: [u8; 32] = cmx_bytes.try_into()
-            .map_err(|_| "Invalid CMX length")?;
-        let cmx = ExtractedNoteCommitment::from_bytes(&cmx_array)
-            .into_option()
-            .ok_or("Invalid CMX")?;
-
-        let ephemeral_key_array: [u8; 32] = ephemeral_key_bytes.try_into()
-            .map_err(|_| "Invalid ephemeral key length")?;
-
-        if ciphertext_bytes.len() != 52 {
-            return Err(format!("Invalid ciphertext length: expected 52, got {}", ciphertext_bytes.len()));
+    // Step 4: Try to decrypt with both External and Internal scopes
+    for prepared_ivk in [&ivk_external, &ivk_internal] {
+        if let Some((note, _recipient)) = try_sapling_compact_note_decryption(prepared_ivk, &compact_output, zip212_enforcement) {
+            // Extract amount from note (in zatoshis)
+            let amount_zatoshis = note.value().inner();
+
+            let output = DecryptedOutput {
+                memo: "[Compact block - memo not available]".to_string(),
+                zatoshis: amount_zatoshis,
+                amount: format_zec(amount_zatoshis),
+                action_index: None,
+                commitment: None,
+            };
+
+            return serde_json::to_string(&output)
+                .map_err(|e| format!("JSON serialization failed: {:?}", e));
         }
-        let ciphertext_array: [u8; 52] = ciphertext_bytes.try_into().unwrap();
+    }
 
-        // Create CompactAction
-        let compact_action = CompactAction::from_parts(
-            nullifier,
-            cmx,
-            ephemeral_key_array.into(),
-            ciphertext_array,
-        );
+    Err("No memo found or viewing key doesn't match this output.".to_string())
+}
 
-        let domain = OrchardDomain::for_compact_action(&compact_action);
-        parsed_outputs.push((domain, compact_action));
+/// Report which pools a UFVK can scan, so callers can skip pools it can't decrypt
+/// A shielded pool only counts if its key component actually parses
+#[wasm_bindgen]
+pub fn ufvk_capabilities(viewing_key: &str) -> Result<String, String> {
+    let (network, ufvk) = Ufvk::decode(viewing_key)
+        .map_err(|e| format!("UFVK decode failed: {:?}", e))?;
+
+    let network_name = match network {
+        NetworkType::Main => "mainnet",
+        NetworkType::Test => "testnet",
+        NetworkType::Regtest => "regtest",
+    };
+
+    let mut capabilities = ViewingKeyCapabilities {
+        orchard: false,
+        sapling: false,
+        transparent: false,
+        network: network_name.to_string(),
+    };
+
+    for fvk in ufvk.items() {
+        match fvk {
+            Fvk::Orchard(data) => {
+                capabilities.orchard = FullViewingKey::from_bytes(&data).is_some();
+            },
+            Fvk::Sapling(data) => {
+                capabilities.sapling = DiversifiableFullViewingKey::from_bytes(&data).is_some();
+            },
+            Fvk::P2pkh(_) => {
+                capabilities.transparent = true;
+            },
+            _ => {
+                // Unknown FVK type, skip
+            }
+        }
     }
 
-    // Step 4: BATCH DECRYPT ALL OUTPUTS AT ONCE!
-    let results = batch::try_compact_note_decryption(&prepared_ivks, &parsed_outputs);
+    serde_json::to_string(&capabilities)
+        .map_err(|e| format!("JSON serialization failed: {:?}", e))
+}
 
-    // Step 5: Collect matching indices and their TXIDs
-    #[derive(serde::Serialize)]
-    struct Match {
-        index: usize,
-        txid: String,
-        height: u64,
-        scope: String,
+// Parsed transaction registry
+//
+// A transaction page calls several inspection functions on the same tx.
+// parse_tx parses it once and returns a handle; the *_handle variants below
+// work on the cached Transaction instead of re-parsing the hex every time.
+// Callers must free_tx the handle when done: there is no eviction, so a
+// handle that is never freed keeps its Transaction in memory for the life
+// of the WASM module.
+
+struct TxRegistry {
+    next_handle: u32,
+    txs: HashMap<u32, Transaction>,
+}
+
+thread_local! {
+    static TX_REGISTRY: RefCell<TxRegistry> = RefCell::new(TxRegistry {
+        next_handle: 1,
+        txs: HashMap::new(),
+    });
+}
+
+/// Run `f` on the cached transaction behind `handle`
+fn with_tx<T>(handle: u32, f: impl FnOnce(&Transaction) -> Result<T, String>) -> Result<T, String> {
+    TX_REGISTRY.with(|registry| {
+        let registry = registry.borrow();
+        let tx = registry.txs.get(&handle)
+            .ok_or_else(|| format!("Unknown transaction handle: {}", handle))?;
+        f(tx)
+    })
+}
+
+/// Parse a transaction once and cache it, returning a handle for the *_handle functions
+#[wasm_bindgen]
+pub fn parse_tx(tx_hex: &str) -> Result<u32, String> {
+    let tx = parse_transaction(tx_hex)?;
+
+    TX_REGISTRY.with(|registry| {
+        let mut registry = registry.borrow_mut();
+
+        // Handles are never 0, and skip any still in use after wrapping around
+        let mut handle = registry.next_handle;
+        while handle == 0 || registry.txs.contains_key(&handle) {
+            handle = handle.wrapping_add(1);
+        }
+        registry.next_handle = handle.wrapping_add(1);
+
+        registry.txs.insert(handle, tx);
+        Ok(handle)
+    })
+}
+
+/// Release a cached transaction. Returns false if the handle was unknown
+#[wasm_bindgen]
+pub fn free_tx(handle: u32) -> bool {
+    TX_REGISTRY.with(|registry| registry.borrow_mut().txs.remove(&handle).is_some())
+}
+
+/// decrypt_memo on a cached transaction
+#[wasm_bindgen]
+pub fn decrypt_memo_handle(
+    handle: u32,
+    viewing_key: &str,
+    min_zatoshis: Option<u64>,
+    include_commitment: Option<bool>,
+) -> Result<String, String> {
+    let result = with_tx(handle, |tx| {
+        decrypt_transaction(tx, viewing_key, min_zatoshis, include_commitment.unwrap_or(false))
+    })?;
+
+    serde_json::to_string(&result)
+        .map_err(|e| format!("JSON serialization failed: {:?}", e))
+}
+
+/// decrypt_memo_cbor on a cached transaction
+#[cfg(feature = "cbor")]
+#[wasm_bindgen]
+pub fn decrypt_memo_cbor_handle(
+    handle: u32,
+    viewing_key: &str,
+    min_zatoshis: Option<u64>,
+    include_commitment: Option<bool>,
+) -> Result<Vec<u8>, String> {
+    let result = with_tx(handle, |tx| {
+        decrypt_transaction(tx, viewing_key, min_zatoshis, include_commitment.unwrap_or(false))
+    })?;
+
+    to_cbor(&result)
+}
+
+/// decrypt_memo_grouped on a cached transaction
+#[wasm_bindgen]
+pub fn decrypt_memo_grouped_handle(handle: u32, viewing_key: &str) -> Result<String, String> {
+    with_tx(handle, |tx| grouped_outputs(tx, viewing_key))
+}
+
+/// get_orchard_action on a cached transaction
+#[wasm_bindgen]
+pub fn get_orchard_action_handle(handle: u32, action_index: usize) -> Result<String, String> {
+    with_tx(handle, |tx| orchard_action_fields(tx, action_index))
+}
+
+/// tx_pools on a cached transaction
+#[wasm_bindgen]
+pub fn tx_pools_handle(handle: u32) -> Result<String, String> {
+    with_tx(handle, pools_of)
+}
+
+/// detect_tx_network on a cached transaction
+#[wasm_bindgen]
+pub fn detect_tx_network_handle(handle: u32) -> Result<String, String> {
+    with_tx(handle, |tx| Ok(network_of(tx).to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orchard::{
+        keys::SpendingKey,
+        note::{Nullifier, RandomSeed, Rho, TransmittedNoteCiphertext},
+        note_encryption::OrchardNoteEncryption,
+        primitives::redpallas::{SpendAuth, VerificationKey},
+        value::{NoteValue, ValueCommitment},
+    };
+
+    fn test_fvk() -> FullViewingKey {
+        let sk = SpendingKey::from_bytes([7u8; 32]).unwrap();
+        FullViewingKey::from(&sk)
     }
 
-    let matches: Vec<Match> = results.iter()
-        .enumerate()
-        .filter_map(|(i, result)| {
-            result.as_ref().map(|((_note, _recipient), ivk_idx)| {
-                let scope_name = if *ivk_idx == 0 { "External" } else { "Internal" };
-                Match {
-                    index: i,
-                    txid: outputs[i].txid.clone(),
-                    height: outputs[i].height,
-                    scope: scope_name.to_string(),
-                }
-            })
+    /// Build an unauthorized action carrying a note encrypted to `fvk` under `scope`
+    /// No proof or signatures, so it can only be used for trial decryption
+    fn encrypted_action(fvk: &FullViewingKey, scope: Scope, value: u64, memo: &str) -> orchard::Action<()> {
+        let nf = Nullifier::from_bytes(&[1u8; 32]).unwrap();
+        let rho = Rho::from_bytes(&nf.to_bytes()).unwrap();
+        let rseed = RandomSeed::from_bytes([2u8; 32], &rho).unwrap();
+        let note = orchard::Note::from_parts(fvk.address_at(0u32, scope), NoteValue::from_raw(value), rho, rseed).unwrap();
+
+        let mut memo_bytes = [0u8; 512];
+        memo_bytes[..memo.len()].copy_from_slice(memo.as_bytes());
+        let encryptor = OrchardNoteEncryption::new(None, note, memo_bytes);
+        let encrypted_note = TransmittedNoteCiphertext {
+            epk_bytes: OrchardDomain::epk_bytes(encryptor.epk()).0,
+            enc_ciphertext: encryptor.encrypt_note_plaintext(),
+            out_ciphertext: [0u8; 80],
+        };
+
+        let ak: [u8; 32] = fvk.to_bytes()[..32].try_into().unwrap();
+        let rk = VerificationKey::<SpendAuth>::try_from(ak).unwrap();
+        let cv_net = ValueCommitment::from_bytes(&[0u8; 32]).unwrap();
+
+        orchard::Action::from_parts(nf, rk, note.commitment().into(), encrypted_note, cv_net, ()).unwrap()
+    }
+
+    /// Copy of `action` with its ephemeral key replaced
+    fn with_epk(action: &orchard::Action<()>, epk_bytes: [u8; 32]) -> orchard::Action<()> {
+        let mut encrypted_note = action.encrypted_note().clone();
+        encrypted_note.epk_bytes = epk_bytes;
+        orchard::Action::from_parts(
+            *action.nullifier(), action.rk().clone(), *action.cmx(), encrypted_note, action.cv_net().clone(), (),
+        ).unwrap()
+    }
+
+    fn ivks(fvk: &FullViewingKey) -> (PreparedIncomingViewingKey, PreparedIncomingViewingKey) {
+        (
+            PreparedIncomingViewingKey::new(&fvk.to_ivk(Scope::External)),
+            PreparedIncomingViewingKey::new(&fvk.to_ivk(Scope::Internal)),
+        )
+    }
+
+    #[test]
+    fn format_zec_is_exact() {
+        assert_eq!(format_zec(0), "0.00000000");
+        assert_eq!(format_zec(1), "0.00000001");
+        assert_eq!(format_zec(30_000_000), "0.30000000");
+        assert_eq!(format_zec(21_000_000 * 100_000_000), "21000000.00000000");
+    }
+
+    #[test]
+    fn coinbase_height_small_ints() {
+        assert_eq!(coinbase_height(&[0x00]), Some(0));
+        for n in 1..=16u8 {
+            assert_eq!(coinbase_height(&[0x50 + n, 0xaa]), Some(u32::from(n)));
+        }
+    }
+
+    #[test]
+    fn coinbase_height_pushes() {
+        assert_eq!(coinbase_height(&[0x01, 0x11]), Some(0x11));
+        assert_eq!(coinbase_height(&[0x02, 0x34, 0x12]), Some(0x1234));
+        assert_eq!(coinbase_height(&[0x03, 0x40, 0x42, 0x0f, 0xff]), Some(1_000_000));
+        assert_eq!(coinbase_height(&[0x04, 0x78, 0x56, 0x34, 0x12]), Some(0x1234_5678));
+    }
+
+    #[test]
+    fn coinbase_height_malformed() {
+        assert_eq!(coinbase_height(&[]), None);
+        assert_eq!(coinbase_height(&[0x03, 0x40, 0x42]), None);
+        assert_eq!(coinbase_height(&[0x05, 0, 0, 0, 0, 0]), None);
+    }
+
+    #[test]
+    fn prepare_ivks_decrypts_own_scope() {
+        let fvk = test_fvk();
+        let (ivk_external, ivk_internal) = prepare_ivks(&test_ufvk(&fvk)).unwrap();
+
+        let received = encrypted_action(&fvk, Scope::External, 50_000, "");
+        let change = encrypted_action(&fvk, Scope::Internal, 20_000, "");
+        let decrypts = |ivk: &PreparedIncomingViewingKey, action: &orchard::Action<()>| {
+            try_note_decryption(&OrchardDomain::for_action(action), ivk, action).is_some()
+        };
+
+        assert!(decrypts(&ivk_external, &received));
+        assert!(!decrypts(&ivk_external, &change));
+        assert!(decrypts(&ivk_internal, &change));
+        assert!(!decrypts(&ivk_internal, &received));
+    }
+
+    /// A v5 transaction with no inputs, outputs or shielded bundles
+    const EMPTY_V5_TX: &str = concat!(
+        "050000800a27a726b4d0d6c2", // header, version group, NU5 branch ID
+        "0000000000000000", // lock time, expiry height
+        "0000", "0000", "00", // transparent, sapling, orchard
+    );
+
+    #[test]
+    fn tx_handle_round_trip() {
+        let handle = parse_tx(EMPTY_V5_TX).unwrap();
+        assert_eq!(tx_pools_handle(handle).unwrap(), "[]");
+        assert_eq!(detect_tx_network_handle(handle).unwrap(), "unknown");
+        assert_eq!(get_orchard_action_handle(handle, 0).unwrap_err(), "No Orchard bundle in transaction");
+        let ufvk = test_ufvk(&test_fvk());
+        assert_eq!(decrypt_memo_handle(handle, &ufvk, None, None).unwrap_err(), "No Orchard bundle in transaction");
+        #[cfg(feature = "cbor")]
+        assert_eq!(decrypt_memo_cbor_handle(handle, &ufvk, None, None).unwrap_err(), "No Orchard bundle in transaction");
+
+        assert!(free_tx(handle));
+        assert!(!free_tx(handle));
+        assert_eq!(tx_pools_handle(handle).unwrap_err(), format!("Unknown transaction handle: {}", handle));
+    }
+
+    #[test]
+    fn compact_sapling_output_decrypts() {
+        use sapling_crypto::{
+            note_encryption::{sapling_note_encryption, SaplingDomain},
+            value::NoteValue as SaplingNoteValue,
+            zip32::ExtendedSpendingKey,
+            Rseed,
+        };
+
+        let dfvk = ExtendedSpendingKey::master(&[7u8; 32]).to_diversifiable_full_viewing_key();
+        let ufvk = Ufvk::try_from_items(vec![Fvk::Sapling(dfvk.to_bytes())]).unwrap()
+            .encode(&NetworkType::Main);
+
+        let (_, address) = dfvk.default_address();
+        let note = sapling_crypto::Note::from_parts(address, SaplingNoteValue::from_raw(40_000), Rseed::AfterZip212([3u8; 32]));
+        let encryptor = sapling_note_encryption(None, note.clone(), [0u8; 512], &mut rand_core::OsRng);
+        let cmu = hex::encode(note.cmu().to_bytes());
+        let epk = hex::encode(SaplingDomain::epk_bytes(encryptor.epk()).0);
+        let ciphertext = hex::encode(&encryptor.encrypt_note_plaintext()[..52]);
+
+        let output: DecryptedOutput = serde_json::from_str(
+            &decrypt_compact_sapling_output(&cmu, &epk, &ciphertext, &ufvk, None).unwrap(),
+        ).unwrap();
+        assert_eq!(output.zatoshis, 40_000);
+
+        // Before Canopy, ZIP 212 (0x02) plaintexts are not valid yet
+        assert!(decrypt_compact_sapling_output(&cmu, &epk, &ciphertext, &ufvk, Some(1_000_000)).is_err());
+        assert!(decrypt_compact_sapling_output(&cmu, &epk, &ciphertext, &ufvk, Some(3_000_000)).is_ok());
+    }
+
+    #[test]
+    fn commitment_matches_on_chain_cmx() {
+        let fvk = test_fvk();
+        let (ivk_external, ivk_internal) = ivks(&fvk);
+        let action = encrypted_action(&fvk, Scope::External, 50_000, "");
+
+        let scan = decrypt_actions([&action], &ivk_external, &ivk_internal, None);
+        let cmx = ExtractedNoteCommitment::from(scan.decrypted[0].note.commitment());
+        assert_eq!(cmx.to_bytes(), action.cmx().to_bytes());
+
+        let with_commitment = summarize_scan(scan, true).unwrap();
+        assert_eq!(with_commitment.outputs[0].commitment, Some(hex::encode(action.cmx().to_bytes())));
+
+        let scan = decrypt_actions([&action], &ivk_external, &ivk_internal, None);
+        let without_commitment = summarize_scan(scan, false).unwrap();
+        assert_eq!(without_commitment.outputs[0].commitment, None);
+        assert!(!serde_json::to_string(&without_commitment).unwrap().contains("commitment"));
+    }
+
+    /// Compact-output JSON entry for `action`, as the batch filter expects it
+    fn compact_entry(action: &orchard::Action<()>, txid: &str, height: u64) -> serde_json::Value {
+        use zcash_note_encryption::ShieldedOutput;
+
+        let compact = CompactAction::from(action);
+        serde_json::json!({
+            "nullifier": hex::encode(compact.nullifier().to_bytes()),
+            "cmx": hex::encode(compact.cmx().to_bytes()),
+            "ephemeral_key": hex::encode(compact.ephemeral_key().0),
+            "ciphertext": hex::encode(compact.enc_ciphertext()),
+            "txid": txid,
+            "height": height,
         })
-        .collect();
+    }
 
-    serde_json::to_string(&matches)
-        .map_err(|e| format!("JSON serialization failed: {:?}", e))
+    fn test_ufvk(fvk: &FullViewingKey) -> String {
+        Ufvk::try_from_items(vec![Fvk::Orchard(fvk.to_bytes())]).unwrap()
+            .encode(&NetworkType::Main)
+    }
+
+    #[test]
+    fn batch_filter_skips_bad_entries_without_shifting_indices() {
+        let fvk = test_fvk();
+        let action = encrypted_action(&fvk, Scope::External, 50_000, "");
+        let mut short = compact_entry(&action, "bb", 2);
+        short["ciphertext"] = serde_json::json!("00");
+        let entries = serde_json::json!([compact_entry(&action, "aa", 1), short, compact_entry(&action, "cc", 3)]);
+
+        let result = batch_filter(&entries.to_string(), &test_ufvk(&fvk)).unwrap();
+        let failed: Vec<usize> = result.failures.iter().map(|f| f.index).collect();
+        assert_eq!(failed, vec![1]);
+        assert_eq!(result.failures[0].txid.as_deref(), Some("bb"));
+
+        // The match after the bad entry keeps its own index and metadata
+        let matched: Vec<(usize, &str, u64)> = result.matches.iter()
+            .map(|m| (m.index, m.txid.as_str(), m.height))
+            .collect();
+        assert_eq!(matched, vec![(0, "aa", 1), (2, "cc", 3)]);
+    }
+
+    #[test]
+    fn batch_filter_isolates_mistyped_entries() {
+        let fvk = test_fvk();
+        let action = encrypted_action(&fvk, Scope::External, 50_000, "");
+        let mut null_ciphertext = compact_entry(&action, "bb", 2);
+        null_ciphertext["ciphertext"] = serde_json::Value::Null;
+        let mut string_height = compact_entry(&action, "cc", 3);
+        string_height["height"] = serde_json::json!("3");
+        let mut missing_cmx = compact_entry(&action, "dd", 4);
+        missing_cmx.as_object_mut().unwrap().remove("cmx");
+        let entries = serde_json::json!([null_ciphertext, string_height, missing_cmx, compact_entry(&action, "ee", 5)]);
+
+        let result = batch_filter(&entries.to_string(), &test_ufvk(&fvk)).unwrap();
+        let failed: Vec<usize> = result.failures.iter().map(|f| f.index).collect();
+        assert_eq!(failed, vec![0, 1, 2]);
+        assert_eq!(result.failures[1].height, None);
+        assert_eq!(result.matches.len(), 1);
+        assert_eq!(result.matches[0].index, 3);
+    }
+
+    #[test]
+    fn totals_reject_values_above_max_money() {
+        let fvk = test_fvk();
+        let (ivk_external, ivk_internal) = ivks(&fvk);
+        // Received + change would wrap a u64 total
+        let received = encrypted_action(&fvk, Scope::External, u64::MAX / 2 + 1, "");
+        let change = encrypted_action(&fvk, Scope::Internal, u64::MAX / 2 + 1, "");
+        let scan = decrypt_actions([&received, &change], &ivk_external, &ivk_internal, None);
+        assert!(summarize_scan(scan, false).is_err());
+
+        // Fits in a u64 but exceeds the supply cap
+        let max = encrypted_action(&fvk, Scope::External, MAX_MONEY, "");
+        let scan = decrypt_actions([&max, &max], &ivk_external, &ivk_internal, None);
+        assert!(summarize_scan(scan, false).is_err());
+
+        let scan = decrypt_actions([&max], &ivk_external, &ivk_internal, None);
+        assert_eq!(summarize_scan(scan, false).unwrap().total_zatoshis, MAX_MONEY);
+    }
+
+    #[test]
+    fn validate_action_rejects_bad_epk() {
+        let action = encrypted_action(&test_fvk(), Scope::External, 50_000, "hi");
+        assert!(validate_action(&action).is_ok());
+
+        // Not a point encoding, and the identity (which OrchardDomain::epk also rejects)
+        assert!(validate_action(&with_epk(&action, [0xff; 32])).is_err());
+        assert!(validate_action(&with_epk(&action, [0u8; 32])).is_err());
+    }
+
+    #[test]
+    fn decrypt_actions_skips_bad_epk() {
+        let fvk = test_fvk();
+        let (ivk_external, ivk_internal) = ivks(&fvk);
+        let good = encrypted_action(&fvk, Scope::External, 50_000, "hello");
+        let bad = with_epk(&good, [0xff; 32]);
+
+        let scan = decrypt_actions([&bad, &good], &ivk_external, &ivk_internal, None);
+        assert_eq!(scan.skipped_actions, vec!["#0: invalid ephemeral key".to_string()]);
+        assert_eq!(scan.decrypted.len(), 1);
+        assert_eq!(scan.decrypted[0].action_index, 1);
+        assert_eq!(scan.decrypted[0].note.value().inner(), 50_000);
+        assert_eq!(scan.decrypted[0].memo, "hello");
+    }
+
+    #[test]
+    fn decrypt_actions_counts_dust() {
+        let fvk = test_fvk();
+        let (ivk_external, ivk_internal) = ivks(&fvk);
+        let dust = encrypted_action(&fvk, Scope::External, 100, "");
+
+        let scan = decrypt_actions([&dust], &ivk_external, &ivk_internal, Some(10_000));
+        assert!(scan.decrypted.is_empty());
+        assert_eq!(scan.filtered_outputs, 1);
+    }
 }
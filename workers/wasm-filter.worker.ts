@@ -119,7 +119,13 @@ async function filterCompactBlocks(
     // Call WASM batch API for this chunk
     const outputsJson = JSON.stringify(allOutputs);
     const matchesJson = wasm.batch_filter_compact_outputs(outputsJson, viewingKey);
-    const matches = JSON.parse(matchesJson);
+    const result = JSON.parse(matchesJson);
+    // Older WASM builds return a bare array of matches
+    const matches = Array.isArray(result) ? result : result.matches;
+    const failures = Array.isArray(result) ? [] : result.failures;
+    if (failures.length > 0) {
+      console.warn(`[WASM Worker] Skipped ${failures.length} malformed outputs`, failures);
+    }
 
     // Convert matches to TXIDs (deduplicate)
     for (const match of matches) {